@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{sync::Mutex, time::Instant};
+
+// Re-read a workspace's branch at most this often; short enough that a
+// branch switch shows up in the next few events without re-reading
+// `.git/HEAD` off disk on every keystroke.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct CachedBranch {
+    branch: Option<String>,
+    fetched_at: Instant,
+}
+
+/// Caches the resolved git branch per workspace root so repeated lookups
+/// for the same workspace don't hit the filesystem until `CACHE_TTL` has
+/// elapsed.
+#[derive(Default)]
+pub struct BranchCache {
+    entries: Mutex<HashMap<String, CachedBranch>>,
+}
+
+impl BranchCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current branch for the repo containing `file_path`,
+    /// reusing a cached value for `workspace_root` if it's still fresh.
+    pub async fn branch_for(&self, workspace_root: &str, file_path: &str) -> Option<String> {
+        let mut entries = self.entries.lock().await;
+        if let Some(cached) = entries.get(workspace_root) {
+            if cached.fetched_at.elapsed() < CACHE_TTL {
+                return cached.branch.clone();
+            }
+        }
+
+        let branch = resolve_branch(Path::new(file_path));
+        entries.insert(
+            workspace_root.to_string(),
+            CachedBranch {
+                branch: branch.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+        branch
+    }
+}
+
+/// Walks up from `path` to the nearest `.git` directory and reads its
+/// `HEAD`, following a `ref: refs/heads/<branch>` indirection or falling
+/// back to a short SHA when the repo is in detached-HEAD state.
+fn resolve_branch(path: &Path) -> Option<String> {
+    let git_dir = find_git_dir(path)?;
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+
+    match head.strip_prefix("ref: ") {
+        Some(rest) => Some(rest.strip_prefix("refs/heads/").unwrap_or(rest).to_string()),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+fn find_git_dir(path: &Path) -> Option<PathBuf> {
+    let mut dir = if path.is_dir() { Some(path) } else { path.parent() };
+    while let Some(d) = dir {
+        let candidate = d.join(".git");
+        if candidate.is_dir() {
+            return Some(candidate);
+        }
+        if candidate.is_file() {
+            return resolve_gitdir_file(&candidate);
+        }
+        dir = d.parent();
+    }
+    None
+}
+
+/// Worktrees and submodules replace `.git` with a file containing a single
+/// `gitdir: <path>` line pointing at the real git dir, which may be relative
+/// to `git_file`'s parent; follow that indirection instead of treating the
+/// workspace as not being in a repo at all.
+fn resolve_gitdir_file(git_file: &Path) -> Option<PathBuf> {
+    let contents = std::fs::read_to_string(git_file).ok()?;
+    let target = contents.trim().strip_prefix("gitdir: ")?;
+    let target = Path::new(target);
+    if target.is_absolute() {
+        Some(target.to_path_buf())
+    } else {
+        Some(git_file.parent()?.join(target))
+    }
+}
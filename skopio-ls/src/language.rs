@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+/// Extension -> language name for the common cases, checked after
+/// `overrides` so a user's config always wins.
+const DEFAULT_EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("go", "Go"),
+    ("rb", "Ruby"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("cc", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("php", "PHP"),
+    ("swift", "Swift"),
+    ("md", "Markdown"),
+    ("json", "JSON"),
+    ("toml", "TOML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("sh", "Shell"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("lua", "Lua"),
+    ("zig", "Zig"),
+];
+
+/// Detects a file's language from its extension, preferring `overrides`
+/// (populated from `SKOPIO_ZED_LANGUAGE_OVERRIDES`) over the built-in map.
+/// Returns `None` for extensionless files or extensions we don't recognize.
+pub fn language_for_path(path: &str, overrides: &HashMap<String, String>) -> Option<String> {
+    let ext = std::path::Path::new(path)
+        .extension()?
+        .to_str()?
+        .to_lowercase();
+
+    if let Some(lang) = overrides.get(&ext) {
+        return Some(lang.clone());
+    }
+
+    DEFAULT_EXTENSIONS
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, lang)| (*lang).to_string())
+}
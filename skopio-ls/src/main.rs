@@ -1,11 +1,11 @@
 use anyhow::Context;
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
-    process::Command,
     sync::Mutex,
     time::{Instant, interval},
 };
@@ -14,9 +14,32 @@ use tower_lsp::{
 };
 use url::Url;
 
+mod git;
+mod language;
+mod sink;
+mod spool;
+mod worker;
+
+use git::BranchCache;
+use sink::{BatchOutcome, CliSink, EmitEvent, EventSink, HttpSink};
+use spool::{Spool, SpooledRecord};
+use worker::{Worker, WorkerManager, WorkerState};
+
+// Which `EventSink` implementation to build, selected via `SKOPIO_ZED_BACKEND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BackendKind {
+    Cli,
+    Http,
+}
+
 #[derive(Debug, Clone)]
-struct CliConfig {
+struct Config {
     skopio_cli: String,
+    backend: BackendKind,
+    // Base URL of the skopio server; only used when `backend` is `Http`.
+    api_url: String,
+    // Bearer token sent with every HTTP request, if set.
+    api_token: Option<String>,
     // Flush current active session after no activity for this duration
     idle_timeout: Duration,
     // Keep sessions alive for this long after switching away;
@@ -28,11 +51,33 @@ struct CliConfig {
     app: String,
     entity_type: String,
     source: String,
+    // Where failed emissions are spooled for later retry.
+    offline_dir: PathBuf,
+    // Backoff applied between retry sweeps of the offline spool; grows
+    // towards `spool_retry_max_backoff` while the sink keeps failing so a
+    // persistently-down backend doesn't get busy-looped.
+    spool_retry_initial_backoff: Duration,
+    spool_retry_max_backoff: Duration,
+    // Maximum number of events sent in a single batch call; larger sweeps
+    // are split into several batch calls of at most this size.
+    batch_size: usize,
+    // Upper bound on how long a flushed event may sit waiting for its batch
+    // before being sent; also doubles as the flush sweep interval.
+    batch_max_latency: Duration,
+    // Extension -> language name overrides, layered over the built-in map.
+    language_overrides: HashMap<String, String>,
 }
 
-impl CliConfig {
+impl Config {
     fn from_env() -> Self {
         let skopio_cli = std::env::var("SKOPIO_CLI_PATH").unwrap_or_else(|_| "skopio-cli".into());
+        let backend = match std::env::var("SKOPIO_ZED_BACKEND").as_deref() {
+            Ok("http") => BackendKind::Http,
+            _ => BackendKind::Cli,
+        };
+        let api_url =
+            std::env::var("SKOPIO_API_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+        let api_token = std::env::var("SKOPIO_API_TOKEN").ok();
         let idle_secs = std::env::var("SKOPIO_ZED_IDLE_SECS")
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
@@ -45,9 +90,31 @@ impl CliConfig {
             .ok()
             .and_then(|v| v.parse::<i64>().ok())
             .unwrap_or(2);
+        let offline_dir = std::env::var("SKOPIO_ZED_OFFLINE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir().join("skopio-zed-offline"));
+        let batch_size = std::env::var("SKOPIO_ZED_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(20);
+        let language_overrides = std::env::var("SKOPIO_ZED_LANGUAGE_OVERRIDES")
+            .ok()
+            .map(|raw| {
+                raw.split(',')
+                    .filter_map(|pair| {
+                        let (ext, lang) = pair.split_once('=')?;
+                        Some((ext.trim().to_lowercase(), lang.trim().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
         Self {
             skopio_cli,
+            backend,
+            api_url,
+            api_token,
             idle_timeout: Duration::from_secs(idle_secs),
             switch_grace: Duration::from_secs(grace_secs),
             min_session_secs,
@@ -55,6 +122,21 @@ impl CliConfig {
             app: "Zed".into(),
             entity_type: "File".into(),
             source: "skopio-zed".into(),
+            offline_dir,
+            spool_retry_initial_backoff: Duration::from_secs(5),
+            spool_retry_max_backoff: Duration::from_secs(300),
+            batch_size,
+            batch_max_latency: Duration::from_secs(5),
+            language_overrides,
+        }
+    }
+
+    fn build_sink(&self) -> Arc<dyn EventSink> {
+        match self.backend {
+            BackendKind::Cli => Arc::new(CliSink::new(self.skopio_cli.clone())),
+            BackendKind::Http => {
+                Arc::new(HttpSink::new(self.api_url.clone(), self.api_token.clone()))
+            }
         }
     }
 }
@@ -67,12 +149,37 @@ fn now_unix_secs() -> i64 {
 }
 
 fn uri_to_path_string(uri: &Url) -> Option<String> {
-    if uri.scheme() != "file" {
+    if uri.scheme() == "file" {
+        return uri
+            .to_file_path()
+            .ok()
+            .map(|p| p.to_string_lossy().to_string());
+    }
+    // Remote URI (Zed's SSH remoting uses schemes other than `file`, e.g.
+    // `ssh://user@host:port/path`): there's no local filesystem path to
+    // resolve, so fall back to the path component of the URI itself.
+    let path = uri.path();
+    if path.is_empty() { None } else { Some(path.to_string()) }
+}
+
+/// Identifies the remote machine a document's URI points at, for any scheme
+/// other than `file`. `None` for local documents.
+fn uri_host(uri: &Url) -> Option<String> {
+    if uri.scheme() == "file" {
         return None;
     }
-    uri.to_file_path()
-        .ok()
-        .map(|p| p.to_string_lossy().to_string())
+    let host = uri.host_str()?;
+    let mut out = String::new();
+    if !uri.username().is_empty() {
+        out.push_str(uri.username());
+        out.push('@');
+    }
+    out.push_str(host);
+    if let Some(port) = uri.port() {
+        out.push(':');
+        out.push_str(&port.to_string());
+    }
+    Some(out)
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +187,15 @@ struct Session {
     uri: Url,
     entity: String,
     project: String,
+    // Set when `uri` points at a remote machine (Zed's SSH-remoted
+    // projects); `None` for local files.
+    host: Option<String>,
+    // Detected once when the session opens; `None` if the extension isn't
+    // recognized.
+    language: Option<String>,
+    // Git branch of the workspace the session's file lives in, resolved
+    // once when the session opens.
+    branch: Option<String>,
     start_ts: i64,
     last_ts: i64,
     last_seen: Instant,
@@ -100,49 +216,335 @@ impl State {
     }
 }
 
-async fn emit_cli_event(cfg: &CliConfig, sess: &Session) -> anyhow::Result<()> {
-    let end_ts = sess.last_ts;
-    let duration = end_ts - sess.start_ts;
+// Builds the sink-agnostic `EmitEvent` for a live session or a replayed
+// spool record, decoupled from their LSP/spool-specific fields so the same
+// emission path serves both.
+fn emit_event_from_session(cfg: &Config, sess: &Session) -> EmitEvent {
+    EmitEvent {
+        // Not yet assigned a spool id: this is a live session, only
+        // spooled (and given an id) if this emission fails.
+        id: None,
+        start_ts: sess.start_ts,
+        end_ts: sess.last_ts,
+        category: cfg.category.clone(),
+        app: cfg.app.clone(),
+        entity: sess.entity.clone(),
+        entity_type: cfg.entity_type.clone(),
+        project: sess.project.clone(),
+        source: cfg.source.clone(),
+        host: sess.host.clone(),
+        language: sess.language.clone(),
+        branch: sess.branch.clone(),
+    }
+}
+
+fn emit_event_from_record(cfg: &Config, record: &SpooledRecord) -> EmitEvent {
+    EmitEvent {
+        id: Some(record.id),
+        start_ts: record.start_ts,
+        end_ts: record.end_ts,
+        category: record.category.clone(),
+        app: record.app.clone(),
+        entity: record.entity.clone(),
+        entity_type: cfg.entity_type.clone(),
+        project: record.project.clone(),
+        source: record.source.clone(),
+        host: record.host.clone(),
+        language: record.language.clone(),
+        branch: record.branch.clone(),
+    }
+}
 
-    if duration < cfg.min_session_secs {
+/// Emits `sess` through `sink`, spooling it for later retry if the sink
+/// fails. The spool write itself failing is returned as an error; the
+/// original emission failure is swallowed once it has been durably queued,
+/// since the record will be retried.
+async fn emit_or_spool(
+    cfg: &Config,
+    sink: &dyn EventSink,
+    spool: &Spool,
+    sess: &Session,
+) -> anyhow::Result<()> {
+    let event = emit_event_from_session(cfg, sess);
+    if event.duration() < cfg.min_session_secs {
         return Ok(());
     }
 
-    let status = Command::new(&cfg.skopio_cli)
-        .arg("event")
-        .arg("--timestamp")
-        .arg(sess.start_ts.to_string())
-        .arg("--category")
-        .arg(&cfg.category)
-        .arg("--app")
-        .arg(&cfg.app)
-        .arg("--entity")
-        .arg(&sess.entity)
-        .arg("--entity-type")
-        .arg(&cfg.entity_type)
-        .arg("--duration")
-        .arg(duration.to_string())
-        .arg("--project")
-        .arg(&sess.project)
-        .arg("--source")
-        .arg(&cfg.source)
-        .arg("--end-timestamp")
-        .arg(end_ts.to_string())
-        .status()
-        .await
-        .with_context(|| format!("Failed to run `{}`", cfg.skopio_cli))?;
-
-    if !status.success() {
-        anyhow::bail!("Skopio CLI exited with status {status}");
+    if sink.emit(&event).await.is_err() {
+        spool
+            .append(SpooledRecord::new(
+                sess.start_ts,
+                sess.last_ts,
+                sess.entity.clone(),
+                sess.project.clone(),
+                cfg.category.clone(),
+                cfg.app.clone(),
+                cfg.source.clone(),
+                sess.host.clone(),
+                sess.language.clone(),
+                sess.branch.clone(),
+            ))
+            .await
+            .with_context(|| "Failed to spool event after emission failure")?;
     }
 
     Ok(())
 }
 
+/// Flushes `sessions` in chunks of at most `cfg.batch_size`, preferring one
+/// `emit_batch` call per chunk over one `emit` call per session. Falls back
+/// to the single-event path for a chunk if the batch call fails outright,
+/// and stops attempting batches for the rest of this worker's lifetime once
+/// the sink signals it doesn't support batching.
+async fn emit_sessions_or_spool(
+    cfg: &Config,
+    sink: &dyn EventSink,
+    spool: &Spool,
+    sessions: &[Session],
+    batch_supported: &mut bool,
+) {
+    for chunk in sessions.chunks(cfg.batch_size.max(1)) {
+        if chunk.len() == 1 || !*batch_supported {
+            for sess in chunk {
+                let _ = emit_or_spool(cfg, sink, spool, sess).await;
+            }
+            continue;
+        }
+
+        let events: Vec<EmitEvent> = chunk
+            .iter()
+            .map(|sess| emit_event_from_session(cfg, sess))
+            .filter(|e| e.duration() >= cfg.min_session_secs)
+            .collect();
+
+        if events.is_empty() {
+            continue;
+        }
+
+        match sink.emit_batch(&events).await {
+            Ok(BatchOutcome::Success) => {}
+            Ok(BatchOutcome::Unsupported) => {
+                *batch_supported = false;
+                for sess in chunk {
+                    let _ = emit_or_spool(cfg, sink, spool, sess).await;
+                }
+            }
+            Err(_) => {
+                // The batch call failed (sink unreachable, non-zero exit,
+                // server error); retry each event in the chunk singly,
+                // spooling any that still fail for later retry.
+                for sess in chunk {
+                    let _ = emit_or_spool(cfg, sink, spool, sess).await;
+                }
+            }
+        }
+    }
+}
+
+/// Attempts to emit every currently queued record once, in order, removing
+/// the ones that succeed. Stops at the first failure (the sink is presumably
+/// still down) rather than burning through the whole backlog. Returns
+/// whether the spool was left empty.
+async fn drain_spool_once(
+    cfg: &Config,
+    sink: &dyn EventSink,
+    spool: &Spool,
+) -> anyhow::Result<bool> {
+    let records = spool.pending().await?;
+    if records.is_empty() {
+        return Ok(true);
+    }
+
+    let mut succeeded_ids = Vec::new();
+    let mut drained = true;
+    for record in &records {
+        let event = emit_event_from_record(cfg, record);
+        if event.duration() < cfg.min_session_secs || sink.emit(&event).await.is_ok() {
+            succeeded_ids.push(record.id);
+        } else {
+            drained = false;
+            break;
+        }
+    }
+
+    if !succeeded_ids.is_empty() {
+        spool.remove(&succeeded_ids).await?;
+    }
+
+    Ok(drained)
+}
+
+/// Worker that periodically retries whatever is sitting in the offline
+/// spool, backing off exponentially while the CLI keeps failing so a
+/// persistently-down CLI doesn't get busy-looped. This is the sole owner of
+/// `drain_spool_once`: nothing else is allowed to call it, since two
+/// concurrent drains would both read the same pending records and emit them
+/// twice before either side removes them.
+struct RetryWorker {
+    cfg: Config,
+    sink: Arc<dyn EventSink>,
+    spool: Arc<Spool>,
+    backoff: Duration,
+    // Skips the initial backoff on the very first step, so records left
+    // over from a previous crash are replayed immediately rather than
+    // waiting `spool_retry_initial_backoff`.
+    first_step: bool,
+}
+
+impl RetryWorker {
+    fn new(cfg: Config, sink: Arc<dyn EventSink>, spool: Arc<Spool>) -> Self {
+        let backoff = cfg.spool_retry_initial_backoff;
+        Self {
+            cfg,
+            sink,
+            spool,
+            backoff,
+            first_step: true,
+        }
+    }
+}
+
+#[tower_lsp::async_trait]
+impl Worker for RetryWorker {
+    fn name(&self) -> &str {
+        "offline-spool-retry"
+    }
+
+    async fn step(&mut self) -> WorkerState {
+        if self.first_step {
+            self.first_step = false;
+        } else {
+            tokio::time::sleep(self.backoff).await;
+        }
+
+        match drain_spool_once(&self.cfg, self.sink.as_ref(), &self.spool).await {
+            Ok(true) => {
+                self.backoff = self.cfg.spool_retry_initial_backoff;
+                WorkerState::Idle
+            }
+            Ok(false) | Err(_) => {
+                self.backoff = (self.backoff * 2).min(self.cfg.spool_retry_max_backoff);
+                WorkerState::Active
+            }
+        }
+    }
+}
+
+/// Worker that sweeps `State` for idle/switched-away sessions and flushes
+/// them, replacing the old bare `tokio::spawn`'d loop: if a sweep panics,
+/// `WorkerManager` restarts it instead of flushing silently stopping.
+struct FlushWorker {
+    cfg: Config,
+    sink: Arc<dyn EventSink>,
+    state: Arc<Mutex<State>>,
+    spool: Arc<Spool>,
+    tick: tokio::time::Interval,
+    // Sticky once the sink signals it doesn't support batching, so we don't
+    // retry a doomed batch call every sweep.
+    batch_supported: bool,
+}
+
+impl FlushWorker {
+    fn new(
+        cfg: Config,
+        sink: Arc<dyn EventSink>,
+        state: Arc<Mutex<State>>,
+        spool: Arc<Spool>,
+    ) -> Self {
+        let tick = interval(cfg.batch_max_latency);
+        Self {
+            cfg,
+            sink,
+            state,
+            spool,
+            tick,
+            batch_supported: true,
+        }
+    }
+}
+
+#[tower_lsp::async_trait]
+impl Worker for FlushWorker {
+    fn name(&self) -> &str {
+        "idle-grace-flusher"
+    }
+
+    async fn step(&mut self) -> WorkerState {
+        self.tick.tick().await;
+
+        let now = Instant::now();
+        let mut to_flush: Vec<Session> = Vec::new();
+
+        {
+            let mut st = self.state.lock().await;
+            let current_key = st.current_key.clone();
+
+            // Idle flush current session
+            if let Some(cur_key) = &current_key {
+                if let Some(cur_sess) = st.sessions.get(cur_key) {
+                    if now.duration_since(cur_sess.last_seen) >= self.cfg.idle_timeout {
+                        if let Some(s) = st.sessions.remove(cur_key) {
+                            to_flush.push(s);
+                        }
+                        st.current_key = None;
+                    }
+                } else {
+                    st.current_key = None;
+                }
+            }
+            let current_key = st.current_key.clone();
+
+            // Grace flush all non-current sessions
+            let grace = self.cfg.switch_grace;
+            let keys_to_remove: Vec<String> = st
+                .sessions
+                .iter()
+                .filter_map(|(k, s)| {
+                    let is_current = current_key.as_deref() == Some(k.as_str());
+                    if is_current {
+                        return None;
+                    }
+                    if now.duration_since(s.last_seen) >= grace {
+                        Some(k.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            for k in keys_to_remove {
+                if let Some(s) = st.sessions.remove(&k) {
+                    to_flush.push(s);
+                }
+            }
+        }
+
+        let flushed_any = !to_flush.is_empty();
+        emit_sessions_or_spool(
+            &self.cfg,
+            self.sink.as_ref(),
+            &self.spool,
+            &to_flush,
+            &mut self.batch_supported,
+        )
+        .await;
+
+        if flushed_any {
+            WorkerState::Active
+        } else {
+            WorkerState::Idle
+        }
+    }
+}
+
 struct Backend {
     client: Client,
-    cfg: CliConfig,
+    cfg: Config,
+    sink: Arc<dyn EventSink>,
     state: Arc<Mutex<State>>,
+    spool: Arc<Spool>,
+    workers: Arc<WorkerManager>,
+    branch_cache: Arc<BranchCache>,
 }
 
 impl Backend {
@@ -152,14 +554,28 @@ impl Backend {
 
         let key = uri.to_string();
 
-        let (entity, project) = {
+        let (entity, project, host, workspace_root, is_new) = {
             let st = self.state.lock().await;
             (
                 uri_to_path_string(&uri).unwrap_or_else(|| key.clone()),
                 st.project_string(),
+                uri_host(&uri),
+                st.workspace_root.clone(),
+                !st.sessions.contains_key(&key),
             )
         };
 
+        // Language/branch are only resolved once, when the session first
+        // opens, not on every keystroke.
+        let (language, branch) = if is_new {
+            let language = language::language_for_path(&entity, &self.cfg.language_overrides);
+            let root = workspace_root.unwrap_or_else(|| entity.clone());
+            let branch = self.branch_cache.branch_for(&root, &entity).await;
+            (language, branch)
+        } else {
+            (None, None)
+        };
+
         let mut st = self.state.lock().await;
 
         // Update or insert session
@@ -175,6 +591,9 @@ impl Backend {
                         uri,
                         entity,
                         project,
+                        host,
+                        language,
+                        branch,
                         start_ts: now_ts,
                         last_ts: now_ts,
                         last_seen: now_instant,
@@ -200,74 +619,24 @@ impl Backend {
         };
 
         if let Some(sess) = maybe {
-            if let Err(err) = emit_cli_event(&self.cfg, &sess).await {
+            if let Err(err) = emit_or_spool(&self.cfg, self.sink.as_ref(), &self.spool, &sess).await
+            {
                 let _ = self
                     .client
                     .log_message(
                         MessageType::ERROR,
-                        format!("Skopio CLI event failed: {err:#}"),
+                        format!("Failed to spool Skopio event: {err:#}"),
                     )
                     .await;
             }
         }
     }
 
-    async fn periodic_flush_tick(cfg: CliConfig, state: Arc<Mutex<State>>) {
-        let mut tick = interval(Duration::from_secs(5));
-        loop {
-            tick.tick().await;
-
-            let now = Instant::now();
-            let mut to_flush: Vec<Session> = Vec::new();
-
-            {
-                let mut st = state.lock().await;
-                let current_key = st.current_key.clone();
-
-                // Idle flush current session
-                if let Some(cur_key) = &current_key {
-                    if let Some(cur_sess) = st.sessions.get(cur_key) {
-                        if now.duration_since(cur_sess.last_seen) >= cfg.idle_timeout {
-                            if let Some(s) = st.sessions.remove(cur_key) {
-                                to_flush.push(s);
-                            }
-                            st.current_key = None;
-                        }
-                    } else {
-                        st.current_key = None;
-                    }
-                }
-                let current_key = st.current_key.clone();
-
-                // Grace flush all non-current sessions
-                let grace = cfg.switch_grace;
-                let keys_to_remove: Vec<String> = st
-                    .sessions
-                    .iter()
-                    .filter_map(|(k, s)| {
-                        let is_current = current_key.as_deref() == Some(k.as_str());
-                        if is_current {
-                            return None;
-                        }
-                        if now.duration_since(s.last_seen) >= grace {
-                            Some(k.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                for k in keys_to_remove {
-                    if let Some(s) = st.sessions.remove(&k) {
-                        to_flush.push(s);
-                    }
-                }
-            }
-
-            for sess in to_flush {
-                let _ = emit_cli_event(&cfg, &sess).await;
-            }
-        }
+    /// Handles the `skopio/workerStatus` custom LSP request, reporting
+    /// whether the background workers (flush sweep, spool retry) are alive.
+    async fn worker_status(&self, _params: serde_json::Value) -> LspResult<serde_json::Value> {
+        let statuses = self.workers.snapshot().await;
+        Ok(serde_json::json!({ "workers": statuses }))
     }
 }
 
@@ -288,6 +657,11 @@ impl LanguageServer for Backend {
             st.workspace_root = root;
         }
 
+        // Replay of whatever was left in the spool from a previous crash is
+        // handled by `RetryWorker`'s first step (it skips its initial
+        // backoff), rather than spawned separately here, so draining only
+        // ever happens from one place at a time.
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
@@ -316,8 +690,37 @@ impl LanguageServer for Backend {
             sessions.extend(st.sessions.drain().map(|(_, v)| v));
             st.current_key = None;
         }
+        // Spool rather than emit synchronously: shelling out to the CLI here
+        // could block editor exit if it hangs or the process is offline.
         for sess in sessions {
-            let _ = emit_cli_event(&self.cfg, &sess).await;
+            let duration = sess.last_ts - sess.start_ts;
+            if duration < self.cfg.min_session_secs {
+                continue;
+            }
+            if let Err(err) = self
+                .spool
+                .append(SpooledRecord::new(
+                    sess.start_ts,
+                    sess.last_ts,
+                    sess.entity.clone(),
+                    sess.project.clone(),
+                    self.cfg.category.clone(),
+                    self.cfg.app.clone(),
+                    self.cfg.source.clone(),
+                    sess.host.clone(),
+                    sess.language.clone(),
+                    sess.branch.clone(),
+                ))
+                .await
+            {
+                let _ = self
+                    .client
+                    .log_message(
+                        MessageType::ERROR,
+                        format!("Failed to spool session on shutdown: {err:#}"),
+                    )
+                    .await;
+            }
         }
         Ok(())
     }
@@ -341,21 +744,45 @@ impl LanguageServer for Backend {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cfg = CliConfig::from_env();
+    let cfg = Config::from_env();
 
     let state = Arc::new(Mutex::new(State {
         workspace_root: None,
         sessions: HashMap::new(),
         current_key: None,
     }));
+    let spool = Arc::new(Spool::open(&cfg.offline_dir).context("Failed to open offline spool")?);
+    let sink = cfg.build_sink();
+    let workers = Arc::new(WorkerManager::new());
+    let branch_cache = Arc::new(BranchCache::new());
+
+    {
+        let cfg = cfg.clone();
+        let sink = sink.clone();
+        let state = state.clone();
+        let spool = spool.clone();
+        workers.spawn(move || {
+            FlushWorker::new(cfg.clone(), sink.clone(), state.clone(), spool.clone())
+        });
+    }
+    {
+        let cfg = cfg.clone();
+        let sink = sink.clone();
+        let spool = spool.clone();
+        workers.spawn(move || RetryWorker::new(cfg.clone(), sink.clone(), spool.clone()));
+    }
 
-    tokio::spawn(Backend::periodic_flush_tick(cfg.clone(), state.clone()));
-
-    let (service, socket) = LspService::new(|client| Backend {
+    let (service, socket) = LspService::build(|client| Backend {
         client,
         cfg: cfg.clone(),
+        sink: sink.clone(),
         state: state.clone(),
-    });
+        spool: spool.clone(),
+        workers: workers.clone(),
+        branch_cache: branch_cache.clone(),
+    })
+    .custom_method("skopio/workerStatus", Backend::worker_status)
+    .finish();
 
     Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
         .serve(service)
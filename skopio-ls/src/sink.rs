@@ -0,0 +1,250 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::process::Command;
+
+/// The fields needed to record one tracked event, independent of whatever
+/// backend (CLI subprocess, HTTP API) ends up delivering it.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmitEvent {
+    // The offline spool's record id, when this event is a replay from the
+    // spool rather than a live session. Sent through as an idempotency key
+    // so a backend that re-observes the same id after an ambiguous failure
+    // (e.g. it persisted the event but the response was lost) can dedupe;
+    // `None` for events emitted straight from a live session.
+    pub id: Option<u64>,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub category: String,
+    pub app: String,
+    pub entity: String,
+    pub entity_type: String,
+    pub project: String,
+    pub source: String,
+    // Set when the event originated on a remote machine (Zed's SSH-remoted
+    // projects); `None` for local files.
+    pub host: Option<String>,
+    // Detected from the file's extension; `None` if unrecognized.
+    pub language: Option<String>,
+    // Git branch of the workspace the file lives in, if resolved.
+    pub branch: Option<String>,
+}
+
+impl EmitEvent {
+    pub fn duration(&self) -> i64 {
+        self.end_ts - self.start_ts
+    }
+}
+
+/// Whether a batch emission attempt fully succeeded, or failed in a way
+/// that suggests this backend doesn't support batching and callers should
+/// fall back to single-event calls from now on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOutcome {
+    Success,
+    Unsupported,
+}
+
+/// A destination events can be emitted to. `CliSink` shells out to
+/// `skopio-cli` (the original behavior); `HttpSink` posts straight to a
+/// skopio server. Both the offline spool and the batching flush sweep are
+/// written against this trait so they work with either backend.
+#[tower_lsp::async_trait]
+pub trait EventSink: Send + Sync {
+    async fn emit(&self, event: &EmitEvent) -> Result<()>;
+    async fn emit_batch(&self, events: &[EmitEvent]) -> Result<BatchOutcome>;
+}
+
+/// Emits events by invoking `skopio-cli`, one process per `emit` call, or a
+/// single `--batch <file>` invocation per `emit_batch` call.
+pub struct CliSink {
+    skopio_cli: String,
+}
+
+/// Whether `stderr` from a failed invocation looks like an arg parser
+/// rejecting `flag` as unrecognized, rather than a runtime failure. Argument
+/// parsers don't agree on an exit code for this (clap uses 2, which is also
+/// used for other usage errors unrelated to flag support), so instead of
+/// hard-coding one, look for `flag` quoted the way parsers quote the
+/// offending token, alongside wording parsers commonly use for "I don't know
+/// this flag". Requiring the quotes avoids matching a flag that merely
+/// shares a prefix with `flag` (e.g. a hypothetical `--batch-size`), and
+/// avoids matching an unrelated validation error that happens to mention
+/// `flag` in passing.
+fn looks_like_unrecognized_flag(stderr: &str, flag: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    let quoted_flag =
+        stderr.contains(&format!("'{flag}'")) || stderr.contains(&format!("\"{flag}\""));
+    quoted_flag
+        && (stderr.contains("unrecognized")
+            || stderr.contains("unexpected argument")
+            || stderr.contains("unknown argument")
+            || stderr.contains("unknown option"))
+}
+
+impl CliSink {
+    pub fn new(skopio_cli: String) -> Self {
+        Self { skopio_cli }
+    }
+
+    fn batch_temp_file_path() -> std::path::PathBuf {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static BATCH_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let id = BATCH_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("skopio-zed-batch-{}-{id}.json", std::process::id()))
+    }
+}
+
+#[tower_lsp::async_trait]
+impl EventSink for CliSink {
+    async fn emit(&self, event: &EmitEvent) -> Result<()> {
+        let mut cmd = Command::new(&self.skopio_cli);
+        cmd.arg("event")
+            .arg("--timestamp")
+            .arg(event.start_ts.to_string())
+            .arg("--category")
+            .arg(&event.category)
+            .arg("--app")
+            .arg(&event.app)
+            .arg("--entity")
+            .arg(&event.entity)
+            .arg("--entity-type")
+            .arg(&event.entity_type)
+            .arg("--duration")
+            .arg(event.duration().to_string())
+            .arg("--project")
+            .arg(&event.project)
+            .arg("--source")
+            .arg(&event.source)
+            .arg("--end-timestamp")
+            .arg(event.end_ts.to_string());
+        if let Some(host) = &event.host {
+            cmd.arg("--host").arg(host);
+        }
+        if let Some(language) = &event.language {
+            cmd.arg("--language").arg(language);
+        }
+        if let Some(branch) = &event.branch {
+            cmd.arg("--branch").arg(branch);
+        }
+        if let Some(id) = event.id {
+            cmd.arg("--event-id").arg(id.to_string());
+        }
+
+        let status = cmd
+            .status()
+            .await
+            .with_context(|| format!("Failed to run `{}`", self.skopio_cli))?;
+
+        if !status.success() {
+            anyhow::bail!("Skopio CLI exited with status {status}");
+        }
+
+        Ok(())
+    }
+
+    async fn emit_batch(&self, events: &[EmitEvent]) -> Result<BatchOutcome> {
+        if events.is_empty() {
+            return Ok(BatchOutcome::Success);
+        }
+
+        let path = Self::batch_temp_file_path();
+        let json = serde_json::to_string(events)?;
+        tokio::fs::write(&path, json)
+            .await
+            .with_context(|| format!("Failed to write batch file {}", path.display()))?;
+
+        let output = Command::new(&self.skopio_cli)
+            .arg("event")
+            .arg("--batch")
+            .arg(&path)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run `{}`", self.skopio_cli));
+
+        let _ = tokio::fs::remove_file(&path).await;
+        let output = output?;
+
+        if output.status.success() {
+            return Ok(BatchOutcome::Success);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if looks_like_unrecognized_flag(&stderr, "--batch") {
+            return Ok(BatchOutcome::Unsupported);
+        }
+        anyhow::bail!("Skopio CLI batch call exited with status {}", output.status);
+    }
+}
+
+/// Emits events by posting them straight to a skopio server over HTTP,
+/// instead of forking `skopio-cli` for every event. Reuses a single
+/// keep-alive `reqwest::Client` across calls.
+pub struct HttpSink {
+    client: reqwest::Client,
+    events_url: String,
+    auth_token: Option<String>,
+}
+
+impl HttpSink {
+    pub fn new(api_url: String, auth_token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            events_url: format!("{}/events", api_url.trim_end_matches('/')),
+            auth_token,
+        }
+    }
+
+    fn post(&self) -> reqwest::RequestBuilder {
+        let req = self.client.post(&self.events_url);
+        match &self.auth_token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+}
+
+#[tower_lsp::async_trait]
+impl EventSink for HttpSink {
+    async fn emit(&self, event: &EmitEvent) -> Result<()> {
+        let resp = self
+            .post()
+            .json(event)
+            .send()
+            .await
+            .context("Failed to POST event to skopio server")?;
+
+        if !resp.status().is_success() {
+            anyhow::bail!("Skopio server rejected event: {}", resp.status());
+        }
+
+        Ok(())
+    }
+
+    async fn emit_batch(&self, events: &[EmitEvent]) -> Result<BatchOutcome> {
+        if events.is_empty() {
+            return Ok(BatchOutcome::Success);
+        }
+
+        let resp = self
+            .post()
+            .json(events)
+            .send()
+            .await
+            .context("Failed to POST batch to skopio server")?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(BatchOutcome::Success);
+        }
+        // Only a missing/disallowed batch route is "this server doesn't
+        // support batching"; anything else (5xx, auth, validation) is a
+        // runtime failure that should be retried instead of permanently
+        // falling back to per-event POSTs that would hit the same error.
+        let route_missing = status == reqwest::StatusCode::NOT_FOUND
+            || status == reqwest::StatusCode::METHOD_NOT_ALLOWED;
+        if route_missing {
+            return Ok(BatchOutcome::Unsupported);
+        }
+        anyhow::bail!("Skopio server rejected batch: {status}");
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::sync::Mutex;
+
+/// A single emission that failed to reach the event sink (or was queued at
+/// shutdown) and is waiting to be retried. Fields mirror the subset of
+/// `Session` needed to re-emit the event later; `entity_type` is not stored
+/// and is taken from the current config on replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpooledRecord {
+    pub id: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub entity: String,
+    pub project: String,
+    pub category: String,
+    pub app: String,
+    pub source: String,
+    // Set when the session's document lived on a remote machine (Zed's
+    // SSH-remoted projects); `None` for local files.
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+impl SpooledRecord {
+    /// Builds a record ready to hand to `Spool::append`, which assigns the
+    /// real id.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start_ts: i64,
+        end_ts: i64,
+        entity: String,
+        project: String,
+        category: String,
+        app: String,
+        source: String,
+        host: Option<String>,
+        language: Option<String>,
+        branch: Option<String>,
+    ) -> Self {
+        Self {
+            id: 0,
+            start_ts,
+            end_ts,
+            entity,
+            project,
+            category,
+            app,
+            source,
+            host,
+            language,
+            branch,
+        }
+    }
+}
+
+/// Append-only, newline-delimited-JSON backed queue of events that failed to
+/// reach the event sink (or were queued at shutdown) and must be retried
+/// later. Records are only removed
+/// once they've been successfully re-emitted, so the spool survives process
+/// crashes and laptop/network outages without losing tracking data.
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    next_id: AtomicU64,
+    // Serializes appends/removals so concurrent writers can't interleave
+    // partial lines or race on the rewrite-on-remove below.
+    write_lock: Mutex<()>,
+}
+
+impl Spool {
+    /// Opens (creating if needed) the spool file under `dir`, scanning it
+    /// once to recover the next id to hand out.
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create offline spool dir {}", dir.display()))?;
+        let path = dir.join("offline_queue.ndjson");
+        if !path.exists() {
+            File::create(&path)
+                .with_context(|| format!("Failed to create spool file {}", path.display()))?;
+        }
+
+        let next_id = Self::read_records(&path)?
+            .iter()
+            .map(|r| r.id + 1)
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self {
+            path,
+            next_id: AtomicU64::new(next_id),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn read_records(path: &Path) -> Result<Vec<SpooledRecord>> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open spool file {}", path.display()))?;
+        BufReader::new(file)
+            .lines()
+            .filter(|line| line.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+            .map(|line| {
+                let line = line?;
+                serde_json::from_str::<SpooledRecord>(&line)
+                    .with_context(|| "Failed to parse spooled record")
+            })
+            .collect()
+    }
+
+    /// Appends `record`, assigning it the next monotonically increasing id.
+    /// Returns the assigned id so callers can key idempotency off it.
+    pub async fn append(&self, mut record: SpooledRecord) -> Result<u64> {
+        let _guard = self.write_lock.lock().await;
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        record.id = id;
+
+        let line = serde_json::to_string(&record)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open spool file {}", self.path.display()))?;
+        writeln!(file, "{line}")?;
+        Ok(id)
+    }
+
+    /// Returns all currently queued records, oldest first.
+    pub async fn pending(&self) -> Result<Vec<SpooledRecord>> {
+        let _guard = self.write_lock.lock().await;
+        Self::read_records(&self.path)
+    }
+
+    /// Removes the records with the given ids by rewriting the spool
+    /// without them. Called once each record has been re-emitted
+    /// successfully so it is never sent twice.
+    pub async fn remove(&self, ids: &[u64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.write_lock.lock().await;
+        let remaining: Vec<SpooledRecord> = Self::read_records(&self.path)?
+            .into_iter()
+            .filter(|r| !ids.contains(&r.id))
+            .collect();
+
+        let tmp_path = self.path.with_extension("ndjson.tmp");
+        {
+            let mut tmp = File::create(&tmp_path)
+                .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+            for record in &remaining {
+                writeln!(tmp, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace spool file {}", self.path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+/// Outcome of one `Worker::step` call, surfaced through
+/// `WorkerManager::snapshot`. `Active` and `Idle` are informational: a worker
+/// is responsible for its own pacing (sleeping/ticking inside `step`), and
+/// the manager keeps calling it and watches for panics. `Done` makes the
+/// manager stop stepping the worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkerState {
+    /// Did useful work and likely has more queued up.
+    Active,
+    /// Ran its check but there was nothing to do.
+    Idle,
+    /// Finished for good; the manager stops stepping it.
+    Done,
+}
+
+/// A unit of background work driven by the `WorkerManager`. Implementors
+/// own their state and advance it one tick at a time, so a panic inside one
+/// worker can be isolated and the worker restarted without taking down the
+/// others or the manager itself.
+#[tower_lsp::async_trait]
+pub trait Worker: Send {
+    fn name(&self) -> &str;
+    async fn step(&mut self) -> WorkerState;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatus {
+    pub name: String,
+    #[serde(skip)]
+    pub last_tick: Instant,
+    pub seconds_since_last_tick: f64,
+    pub error_count: u64,
+    pub restart_count: u64,
+    pub last_state: WorkerState,
+}
+
+impl WorkerStatus {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            last_tick: Instant::now(),
+            seconds_since_last_tick: 0.0,
+            error_count: 0,
+            restart_count: 0,
+            last_state: WorkerState::Idle,
+        }
+    }
+
+    fn record_tick(&mut self, state: WorkerState) {
+        self.last_tick = Instant::now();
+        self.last_state = state;
+    }
+
+    fn record_restart(&mut self) {
+        self.error_count += 1;
+        self.restart_count += 1;
+    }
+}
+
+/// Owns the set of background workers and drives each in its own supervised
+/// task. If a worker panics, the manager rebuilds it from its factory and
+/// restarts it after a backoff, rather than letting the panic silently stop
+/// that worker's loop.
+#[derive(Debug, Clone)]
+pub struct WorkerManager {
+    statuses: Arc<Mutex<Vec<WorkerStatus>>>,
+}
+
+impl WorkerManager {
+    pub fn new() -> Self {
+        Self {
+            statuses: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Spawns a supervised task that builds a worker via `factory`, steps it
+    /// in a loop, and rebuilds + restarts it (with exponential backoff) if a
+    /// step ever panics.
+    pub fn spawn<W, F>(&self, factory: F)
+    where
+        W: Worker + 'static,
+        F: Fn() -> W + Send + 'static,
+    {
+        let statuses = self.statuses.clone();
+
+        tokio::spawn(async move {
+            let mut worker = factory();
+            let name = worker.name().to_string();
+
+            let index = {
+                let mut list = statuses.lock().await;
+                list.push(WorkerStatus::new(name));
+                list.len() - 1
+            };
+
+            let mut restart_backoff = Duration::from_millis(500);
+            const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+            let mut current = Some(worker);
+            loop {
+                let mut w = current.take().expect("worker present between iterations");
+                match tokio::spawn(async move {
+                    let state = w.step().await;
+                    (w, state)
+                })
+                .await
+                {
+                    Ok((w_back, state)) => {
+                        {
+                            let mut list = statuses.lock().await;
+                            list[index].record_tick(state);
+                        }
+                        if state == WorkerState::Done {
+                            break;
+                        }
+                        restart_backoff = Duration::from_millis(500);
+                        current = Some(w_back);
+                    }
+                    Err(_join_err) => {
+                        {
+                            let mut list = statuses.lock().await;
+                            list[index].record_restart();
+                        }
+                        tokio::time::sleep(restart_backoff).await;
+                        restart_backoff = (restart_backoff * 2).min(MAX_RESTART_BACKOFF);
+                        current = Some(factory());
+                    }
+                }
+            }
+        });
+    }
+
+    /// Returns a point-in-time view of every worker's liveness, suitable for
+    /// serializing straight into an LSP response.
+    pub async fn snapshot(&self) -> Vec<WorkerStatus> {
+        let list = self.statuses.lock().await;
+        list.iter()
+            .cloned()
+            .map(|mut s| {
+                s.seconds_since_last_tick = s.last_tick.elapsed().as_secs_f64();
+                s
+            })
+            .collect()
+    }
+}
+
+impl Default for WorkerManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}